@@ -1,8 +1,12 @@
 //! Small bioinformatics library for Rust
 
+extern crate flate2;
+
 use std::fs::File;
 use std::io::Read;
 
+use flate2::read::GzDecoder;
+
 /// Represents a base
 #[repr(u8)]
 #[derive(Debug)]
@@ -21,14 +25,11 @@ impl Base {
   /// assert_eq!(thyamine, hemoglobin::Base::T);
   /// ```
   pub fn complement(&self) -> Self {
-    match *self {
-      Base::A => Base::T,
-      Base::T => Base::A,
-      Base::C => Base::G,
-      Base::G => Base::C
-    }
+    // A<->T and C<->G are exactly the bit-pairs that differ in both bits,
+    // so complementing is XOR with 0b11
+    Base::from_u64((*self as u64) ^ 0b11).unwrap()
   }
-  
+
   /// Convert a u64 number to a Base
   ///
   /// # Examples
@@ -70,46 +71,448 @@ pub struct BaseCount {
   pub T: u64
 }
 
-/// Represents a sequence of bases
+/// Represents an amino acid (or a stop codon)
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(Eq, PartialEq)]
+pub enum AminoAcid {
+  Ala, Arg, Asn, Asp, Cys, Gln, Glu, Gly, His, Ile, Leu, Lys, Met, Phe, Pro,
+  Ser, Thr, Trp, Tyr, Val, Stop
+}
+
+impl std::fmt::Display for AminoAcid {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    f.write_str(match *self {
+      AminoAcid::Ala => "A", AminoAcid::Arg => "R", AminoAcid::Asn => "N",
+      AminoAcid::Asp => "D", AminoAcid::Cys => "C", AminoAcid::Gln => "Q",
+      AminoAcid::Glu => "E", AminoAcid::Gly => "G", AminoAcid::His => "H",
+      AminoAcid::Ile => "I", AminoAcid::Leu => "L", AminoAcid::Lys => "K",
+      AminoAcid::Met => "M", AminoAcid::Phe => "F", AminoAcid::Pro => "P",
+      AminoAcid::Ser => "S", AminoAcid::Thr => "T", AminoAcid::Trp => "W",
+      AminoAcid::Tyr => "Y", AminoAcid::Val => "V", AminoAcid::Stop => "*"
+    })
+  }
+}
+
+/// A reading frame outside `1`/`2`/`3`/`-1`/`-2`/`-3`, returned by
+/// `Sequence::translate`
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(Eq, PartialEq)]
+pub struct InvalidFrame(pub i8);
+
+impl std::fmt::Display for InvalidFrame {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write!(f, "frame {} is not one of 1, 2, 3, -1, -2, -3", self.0)
+  }
+}
+
+impl std::error::Error for InvalidFrame {}
+
+// Standard genetic code, indexed by (b0<<4)|(b1<<2)|b2 where b0/b1/b2 are
+// the Base discriminants of a codon's three bases in order
+const CODON_TABLE: [AminoAcid; 64] = [
+  AminoAcid::Lys, AminoAcid::Asn, AminoAcid::Lys, AminoAcid::Asn, // AAA AAC AAG AAT
+  AminoAcid::Thr, AminoAcid::Thr, AminoAcid::Thr, AminoAcid::Thr, // ACA ACC ACG ACT
+  AminoAcid::Arg, AminoAcid::Ser, AminoAcid::Arg, AminoAcid::Ser, // AGA AGC AGG AGT
+  AminoAcid::Ile, AminoAcid::Ile, AminoAcid::Met, AminoAcid::Ile, // ATA ATC ATG ATT
+  AminoAcid::Gln, AminoAcid::His, AminoAcid::Gln, AminoAcid::His, // CAA CAC CAG CAT
+  AminoAcid::Pro, AminoAcid::Pro, AminoAcid::Pro, AminoAcid::Pro, // CCA CCC CCG CCT
+  AminoAcid::Arg, AminoAcid::Arg, AminoAcid::Arg, AminoAcid::Arg, // CGA CGC CGG CGT
+  AminoAcid::Leu, AminoAcid::Leu, AminoAcid::Leu, AminoAcid::Leu, // CTA CTC CTG CTT
+  AminoAcid::Glu, AminoAcid::Asp, AminoAcid::Glu, AminoAcid::Asp, // GAA GAC GAG GAT
+  AminoAcid::Ala, AminoAcid::Ala, AminoAcid::Ala, AminoAcid::Ala, // GCA GCC GCG GCT
+  AminoAcid::Gly, AminoAcid::Gly, AminoAcid::Gly, AminoAcid::Gly, // GGA GGC GGG GGT
+  AminoAcid::Val, AminoAcid::Val, AminoAcid::Val, AminoAcid::Val, // GTA GTC GTG GTT
+  AminoAcid::Stop, AminoAcid::Tyr, AminoAcid::Stop, AminoAcid::Tyr, // TAA TAC TAG TAT
+  AminoAcid::Ser, AminoAcid::Ser, AminoAcid::Ser, AminoAcid::Ser, // TCA TCC TCG TCT
+  AminoAcid::Stop, AminoAcid::Cys, AminoAcid::Trp, AminoAcid::Cys, // TGA TGC TGG TGT
+  AminoAcid::Leu, AminoAcid::Phe, AminoAcid::Leu, AminoAcid::Phe, // TTA TTC TTG TTT
+];
+
+/// Maps a codec's symbols to and from the bitfield `Sequence<C>` packs them
+/// into, so storage width and parsing can vary by alphabet while sharing
+/// one packed representation
+pub trait Codec {
+  /// The decoded type this codec's bitfields represent
+  type Symbol: Copy + Eq + std::fmt::Debug;
+
+  /// Number of bits used to store one symbol. Must evenly divide 64
+  const BITS: u32;
+
+  /// Parse a single character, or None if it isn't part of this codec's
+  /// alphabet
+  fn encode(c: char) -> Option<Self::Symbol>;
+
+  /// Render a symbol back to the character it was parsed from
+  fn to_char(symbol: Self::Symbol) -> char;
+
+  /// Complement of a symbol, e.g. the IUPAC ambiguity class it maps to
+  fn complement(symbol: Self::Symbol) -> Self::Symbol;
+
+  /// Pack a symbol into the low bits of a u64
+  fn to_bits(symbol: Self::Symbol) -> u64;
+
+  /// Unpack a symbol from the low bits of a u64. Only ever called with
+  /// values `to_bits` could have produced, so is expected to always succeed
+  fn from_bits(bits: u64) -> Self::Symbol;
+
+  /// If this codec's `complement` is the same XOR for every symbol (as for
+  /// DNA, where A<->T and C<->G are both `symbol XOR 0b11`), the mask to
+  /// XOR a whole packed word with to complement every symbol in it at once.
+  /// `None` for codecs like `Iupac`, whose complement isn't a fixed XOR, so
+  /// `Sequence::complement` falls back to complementing symbol-by-symbol
+  const WORD_COMPLEMENT_MASK: Option<u64> = None;
+}
+
+/// The plain 2-bit A/C/G/T codec used throughout this crate
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(Eq, PartialEq)]
+pub struct Dna;
+
+impl Codec for Dna {
+  type Symbol = Base;
+
+  const BITS: u32 = 2;
+
+  // A<->T and C<->G are exactly the bit-pairs that differ in both bits, so
+  // complementing is XOR with 0b11, repeated across every symbol in the
+  // word that's XOR with a word of all-ones
+  const WORD_COMPLEMENT_MASK: Option<u64> = Some(u64::MAX);
+
+  fn encode(c: char) -> Option<Base> {
+    match c {
+      'A' => Some(Base::A),
+      'C' => Some(Base::C),
+      'G' => Some(Base::G),
+      'T' => Some(Base::T),
+      _ => None
+    }
+  }
+
+  fn to_char(symbol: Base) -> char {
+    match symbol {
+      Base::A => 'A', Base::C => 'C', Base::G => 'G', Base::T => 'T'
+    }
+  }
+
+  fn complement(symbol: Base) -> Base {
+    symbol.complement()
+  }
+
+  fn to_bits(symbol: Base) -> u64 {
+    symbol as u64
+  }
+
+  fn from_bits(bits: u64) -> Base {
+    Base::from_u64(bits).unwrap()
+  }
+}
+
+/// IUPAC nucleotide ambiguity code (the full alphabet: exact bases, the
+/// eleven ambiguity classes, and a gap)
+#[repr(u8)]
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(Eq, PartialEq)]
+pub enum IupacCode {
+  A = 0, C = 1, G = 2, T = 3, R = 4, Y = 5, S = 6, W = 7,
+  K = 8, M = 9, B = 10, D = 11, H = 12, V = 13, N = 14, Gap = 15
+}
+
+/// The 4-bit IUPAC ambiguity codec, for sequences that may contain N or
+/// other ambiguity codes in addition to A/C/G/T
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(Eq, PartialEq)]
+pub struct Iupac;
+
+impl Codec for Iupac {
+  type Symbol = IupacCode;
+
+  const BITS: u32 = 4;
+
+  fn encode(c: char) -> Option<IupacCode> {
+    match c {
+      'A' => Some(IupacCode::A), 'C' => Some(IupacCode::C),
+      'G' => Some(IupacCode::G), 'T' => Some(IupacCode::T),
+      'R' => Some(IupacCode::R), 'Y' => Some(IupacCode::Y),
+      'S' => Some(IupacCode::S), 'W' => Some(IupacCode::W),
+      'K' => Some(IupacCode::K), 'M' => Some(IupacCode::M),
+      'B' => Some(IupacCode::B), 'D' => Some(IupacCode::D),
+      'H' => Some(IupacCode::H), 'V' => Some(IupacCode::V),
+      'N' => Some(IupacCode::N), '-' => Some(IupacCode::Gap),
+      _ => None
+    }
+  }
+
+  fn to_char(symbol: IupacCode) -> char {
+    match symbol {
+      IupacCode::A => 'A', IupacCode::C => 'C',
+      IupacCode::G => 'G', IupacCode::T => 'T',
+      IupacCode::R => 'R', IupacCode::Y => 'Y',
+      IupacCode::S => 'S', IupacCode::W => 'W',
+      IupacCode::K => 'K', IupacCode::M => 'M',
+      IupacCode::B => 'B', IupacCode::D => 'D',
+      IupacCode::H => 'H', IupacCode::V => 'V',
+      IupacCode::N => 'N', IupacCode::Gap => '-'
+    }
+  }
+
+  fn complement(symbol: IupacCode) -> IupacCode {
+    match symbol {
+      IupacCode::A => IupacCode::T, IupacCode::T => IupacCode::A,
+      IupacCode::C => IupacCode::G, IupacCode::G => IupacCode::C,
+      IupacCode::R => IupacCode::Y, IupacCode::Y => IupacCode::R,
+      IupacCode::S => IupacCode::S, IupacCode::W => IupacCode::W,
+      IupacCode::K => IupacCode::M, IupacCode::M => IupacCode::K,
+      IupacCode::B => IupacCode::V, IupacCode::V => IupacCode::B,
+      IupacCode::D => IupacCode::H, IupacCode::H => IupacCode::D,
+      IupacCode::N => IupacCode::N, IupacCode::Gap => IupacCode::Gap
+    }
+  }
+
+  fn to_bits(symbol: IupacCode) -> u64 {
+    symbol as u64
+  }
+
+  fn from_bits(bits: u64) -> IupacCode {
+    match bits {
+      0 => IupacCode::A, 1 => IupacCode::C, 2 => IupacCode::G, 3 => IupacCode::T,
+      4 => IupacCode::R, 5 => IupacCode::Y, 6 => IupacCode::S, 7 => IupacCode::W,
+      8 => IupacCode::K, 9 => IupacCode::M, 10 => IupacCode::B, 11 => IupacCode::D,
+      12 => IupacCode::H, 13 => IupacCode::V, 14 => IupacCode::N, 15 => IupacCode::Gap,
+      _ => unreachable!("IupacCode is 4 bits wide")
+    }
+  }
+}
+
+/// A character that isn't part of the codec's alphabet, returned by
+/// `Sequence::try_new`
+#[derive(Debug)]
+#[derive(Copy, Clone)]
+#[derive(Eq, PartialEq)]
+pub struct InvalidCharacter(pub char);
+
+impl std::fmt::Display for InvalidCharacter {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write!(f, "character {:?} is not valid for this codec", self.0)
+  }
+}
+
+impl std::error::Error for InvalidCharacter {}
+
+fn symbols_per_word<C: Codec>() -> usize {
+  64 / (C::BITS as usize)
+}
+
+fn word_and_shift<C: Codec>(i: usize) -> (usize, u32) {
+  let per_word = symbols_per_word::<C>();
+  (i / per_word, 64 - C::BITS*((i % per_word) as u32 + 1))
+}
+
+/// Reverse the order of the `C::BITS`-wide symbol groups packed into a
+/// single word, keeping each group's bits intact
+fn reverse_word_symbols<C: Codec>(word: u64) -> u64 {
+  let per_word = symbols_per_word::<C>();
+  let mask = (1 << C::BITS) - 1;
+  let mut result = 0u64;
+
+  for k in 0..per_word {
+    let src_shift = 64 - C::BITS*(k as u32 + 1);
+    let dst_shift = 64 - C::BITS*((per_word - 1 - k) as u32 + 1);
+    result |= ((word >> src_shift) & mask) << dst_shift;
+  }
+
+  result
+}
+
+/// Represents a sequence of symbols from codec `C`, packed `C::BITS` bits
+/// per symbol. Defaults to the plain 2-bit `Dna` codec
 #[derive(Debug)]
 #[derive(Clone)]
 #[derive(Eq, PartialEq)]
-pub struct Sequence(pub Vec<Base>);
+pub struct Sequence<C: Codec = Dna> {
+  data: Vec<u64>,
+  len: usize,
+  codec: std::marker::PhantomData<C>
+}
 
-impl std::fmt::Display for Sequence {
+impl<C: Codec> std::fmt::Display for Sequence<C> {
   fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-    for base in &self.0 {
-      try!(base.fmt(f));
+    for i in 0..self.len {
+      try!(write!(f, "{}", C::to_char(self.get(i).unwrap())));
     }
-    
+
     return Ok(());
   }
 }
 
-impl Sequence {
-  /// Constructs a new Sequence
+impl<C: Codec> Sequence<C> {
+  fn empty() -> Self {
+    Sequence { data: Vec::new(), len: 0, codec: std::marker::PhantomData }
+  }
+
+  fn push(&mut self, symbol: C::Symbol) {
+    let per_word = symbols_per_word::<C>();
+
+    if self.len % per_word == 0 {
+      self.data.push(0);
+    }
+
+    let (word, shift) = word_and_shift::<C>(self.len);
+    self.data[word] |= C::to_bits(symbol) << shift;
+    self.len += 1;
+  }
+
+  /// Constructs a new Sequence, failing on the first character that isn't
+  /// part of the codec's alphabet
+  ///
+  /// # Examples
+  /// ```
+  /// use hemoglobin::{Sequence, Iupac};
+  ///
+  /// assert!(Sequence::<Iupac>::try_new("ACGN").is_ok());
+  /// assert_eq!(Sequence::<Iupac>::try_new("ACGZ").unwrap_err().0, 'Z');
+  /// ```
+  pub fn try_new<S: Into<String>>(s: S) -> Result<Self, InvalidCharacter> {
+    let mut result = Self::empty();
+
+    for c in s.into().chars() {
+      match C::encode(c) {
+        Some(symbol) => result.push(symbol),
+        None => return Err(InvalidCharacter(c))
+      }
+    }
+
+    return Ok(result);
+  }
+
+  /// Number of symbols in the sequence
+  pub fn len(&self) -> usize {
+    return self.len;
+  }
+
+  /// Whether the sequence has no symbols
+  pub fn is_empty(&self) -> bool {
+    return self.len == 0;
+  }
+
+  /// Get the symbol at a given position
+  ///
+  /// # Examples
+  /// ```
+  /// let a_sequence = hemoglobin::Sequence::new("ATCG");
+  ///
+  /// assert_eq!(a_sequence.get(2), Some(hemoglobin::Base::C));
+  /// assert_eq!(a_sequence.get(4), None);
+  /// ```
+  pub fn get(&self, i: usize) -> Option<C::Symbol> {
+    if i >= self.len {
+      return None;
+    }
+
+    let (word, shift) = word_and_shift::<C>(i);
+    return Some(C::from_bits((self.data[word] >> shift) & ((1 << C::BITS) - 1)));
+  }
+
+  /// Complement of every symbol, in the same order. When the codec's
+  /// complement is a uniform word XOR (as for `Dna`), this runs as a batch
+  /// of word-level ops instead of one `complement` call per symbol
+  ///
+  /// # Examples
+  /// ```
+  /// let a_sequence = hemoglobin::Sequence::new("TACG");
+  ///
+  /// assert_eq!(a_sequence.complement(), hemoglobin::Sequence::new("ATGC"));
+  /// ```
+  pub fn complement(&self) -> Self {
+    if let Some(mask) = C::WORD_COMPLEMENT_MASK {
+      let mut data: Vec<u64> = self.data.iter().map(|word| word ^ mask).collect();
+
+      if let Some(last) = data.last_mut() {
+        let per_word = symbols_per_word::<C>();
+        let used_in_last = self.len - (self.data.len() - 1)*per_word;
+        if used_in_last < per_word {
+          *last &= u64::MAX << (C::BITS as usize*(per_word - used_in_last));
+        }
+      }
+
+      return Sequence { data, len: self.len, codec: std::marker::PhantomData };
+    }
+
+    let mut result = Self::empty();
+
+    for i in 0..self.len {
+      result.push(C::complement(self.get(i).unwrap()));
+    }
+
+    return result;
+  }
+
+  /// Reverse the sequence and take its complement. Reversing is a word-level
+  /// op: the word order and the symbol order within each word are both
+  /// reversed, then the whole buffer is bit-shifted to move the unused tail
+  /// of the last word (now leading) back to the end, all without decoding
+  /// symbols one at a time
+  ///
+  /// # Examples
+  /// ```
+  /// let a_sequence = hemoglobin::Sequence::new("TACGATCTAGTCTAGGATC");
+  /// let reverse_complement = a_sequence.reverse_complement();
+  ///
+  /// assert_eq!(reverse_complement, hemoglobin::Sequence::new("GATCCTAGACTAGATCGTA"));
+  /// ```
+  pub fn reverse_complement(&self) -> Self {
+    let complemented = self.complement();
+
+    if complemented.data.is_empty() {
+      return complemented;
+    }
+
+    let per_word = symbols_per_word::<C>();
+    let used_in_last = self.len - (complemented.data.len() - 1)*per_word;
+    let pad = (per_word - used_in_last)*(C::BITS as usize);
+
+    let mut data: Vec<u64> = complemented.data.iter().rev()
+      .map(|&word| reverse_word_symbols::<C>(word))
+      .collect();
+
+    if pad > 0 {
+      for i in 0..data.len() {
+        let spill = if i + 1 < data.len() { data[i + 1] >> (64 - pad) } else { 0 };
+        data[i] = (data[i] << pad) | spill;
+      }
+    }
+
+    return Sequence { data, len: self.len, codec: std::marker::PhantomData };
+  }
+}
+
+impl Sequence<Dna> {
+  /// Constructs a new Sequence, silently dropping any character that isn't
+  /// A/C/G/T. Use `try_new` (available for any codec) to catch those instead
   ///
   /// # Examples
   /// ```
   /// let a_sequence = hemoglobin::Sequence::new("ATCG");
   /// ```
   pub fn new<S: Into<String>>(s: S) -> Self {
-    let mut result: Vec<Base> = Vec::new();
-    
-    for v in s.into().chars() {
-      match v {
-        'A' => result.push(Base::A),
-        'T' => result.push(Base::T),
-        'C' => result.push(Base::C),
-        'G' => result.push(Base::G),
-        _ => {}
+    let mut result = Self::empty();
+
+    for c in s.into().chars() {
+      if let Some(symbol) = Dna::encode(c) {
+        result.push(symbol);
       }
     }
-    
-    return Sequence(result);
+
+    return result;
   }
-  
-  /// Constructs a new Sequence from a file
+
+  /// Constructs a new Sequence from a file. Transparently decompresses
+  /// gzip-compressed files (detected by their magic bytes), same as
+  /// `from_reader`
   ///
   /// # Examples
   /// ```
@@ -117,14 +520,45 @@ impl Sequence {
   /// let a_sequence = hemoglobin::Sequence::from_file(path);
   /// ```
   pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<Self> {
-    let mut file = try!(File::open(path));
-    let mut contents: String = String::new();
-    try!(file.read_to_string(&mut contents));
-    
-    let result = Sequence::new(contents);
-    return Ok(result);
+    let file = try!(File::open(path));
+    return Self::from_reader(file);
   }
-  
+
+  /// Constructs a new Sequence by reading from any `Read`, so compressed or
+  /// networked streams can be fed in directly. If the stream starts with
+  /// the gzip magic bytes (`1f 8b`) it's inflated first; otherwise it's
+  /// read as plain text
+  ///
+  /// # Examples
+  /// ```
+  /// let contents = std::io::Cursor::new(b"ATCG".to_vec());
+  /// let a_sequence = hemoglobin::Sequence::from_reader(contents).unwrap();
+  ///
+  /// assert_eq!(a_sequence, hemoglobin::Sequence::new("ATCG"));
+  /// ```
+  pub fn from_reader<R: Read>(mut reader: R) -> std::io::Result<Self> {
+    let mut header = [0u8; 2];
+    let mut header_len = 0;
+
+    while header_len < header.len() {
+      match try!(reader.read(&mut header[header_len..])) {
+        0 => break,
+        n => header_len += n
+      }
+    }
+
+    let mut chained = std::io::Cursor::new(header[..header_len].to_vec()).chain(reader);
+    let mut contents = String::new();
+
+    if header_len == 2 && header == [0x1f, 0x8b] {
+      try!(GzDecoder::new(chained).read_to_string(&mut contents));
+    } else {
+      try!(chained.read_to_string(&mut contents));
+    }
+
+    return Ok(Self::new(contents));
+  }
+
   /// Convert a bitfield in a u64 to a Sequence
   ///
   /// # Examples
@@ -134,17 +568,17 @@ impl Sequence {
   /// assert_eq!(a_sequence, hemoglobin::Sequence::new("GTGGCCTGCC"));
   /// ```
   pub fn from_u64(num: u64, length: u8) -> Self {
-    let mut result: Vec<Base> = Vec::new();
-    
+    let mut result = Self::empty();
+
     let end = std::cmp::min(length, 32);
-    
+
     for i in 0..end {
       result.push(Base::from_u64(num << 2*i >> 62).unwrap()); // Result guaranteed to exist for numbers <= 3
     }
-    
-    return Sequence(result);
+
+    return result;
   }
-  
+
   /// Retrieve a subsequence, encoded as a bitfield in a u64. Limited to 32 bases
   ///
   /// # Examples
@@ -155,19 +589,19 @@ impl Sequence {
   /// assert_eq!(numbers, 0x372C000000000000);
   /// ```
   pub fn subsequence_as_u64(&self, start: usize, length: usize) -> Option<u64> {
-    if length > 32 || start + length > self.0.len() {
+    if length > 32 || start + length > self.len {
       return None;
     }
-    
+
     let mut result: u64 = 0;
-    
+
     for i in 0..length {
-      result = result | ((self.0[start + i] as u64) << (62 - 2*i));
+      result = result | ((self.get(start + i).unwrap() as u64) << (62 - 2*i));
     }
-    
+
     return Some(result);
   }
-  
+
   /// Count bases in a sequence
   ///
   /// # Examples
@@ -180,36 +614,207 @@ impl Sequence {
   /// ```
   pub fn count_bases(&self) -> BaseCount {
     let mut result = BaseCount { A: 0, C: 0, G: 0, T: 0 };
-    
-    for base in &self.0 {
-      match *base {
+
+    for i in 0..self.len {
+      match self.get(i).unwrap() {
         Base::A => result.A += 1,
         Base::C => result.C += 1,
         Base::G => result.G += 1,
         Base::T => result.T += 1
       }
     }
-    
+
     return result;
   }
-  
-  /// Reverse sequence and take its complement
+
+  /// Iterate over the 2-bit encoding of every length-`k` window in the
+  /// sequence, in O(n) total via a rolling register. Each value packs the
+  /// window's bases first-base-most-significant, like `subsequence_as_u64`,
+  /// but right-aligned in the low `2*k` bits rather than left-aligned.
+  /// Empty if `k` is 0 or larger than 32 or than the sequence itself
+  ///
+  /// # Examples
+  /// ```
+  /// let a_sequence = hemoglobin::Sequence::new("ATCGA");
+  /// let kmers: Vec<u64> = a_sequence.kmers(3).collect();
+  ///
+  /// assert_eq!(kmers, vec![0xd, 0x36, 0x18]);
+  /// ```
+  pub fn kmers(&self, k: usize) -> Kmers<'_> {
+    let mask = if k >= 32 { u64::MAX } else { (1 << (2*k)) - 1 };
+
+    return Kmers { sequence: self, k, i: 0, register: 0, mask };
+  }
+
+  /// Select a canonical minimizer from each window of `w` consecutive
+  /// `k`-mers, returning each selection's position and value.
+  /// Consecutive duplicate selections (the common case of a minimizer
+  /// surviving into the next window) are collapsed to one entry. Canonical
+  /// here means the smaller of a k-mer's forward and reverse-complement
+  /// encodings, so the same underlying site hashes the same on either
+  /// strand
   ///
   /// # Examples
   /// ```
   /// let a_sequence = hemoglobin::Sequence::new("TACGATCTAGTCTAGGATC");
-  /// let reverse_complement = a_sequence.reverse_complement();
+  /// let minimizers = a_sequence.minimizers(4, 3);
   ///
-  /// assert_eq!(reverse_complement, hemoglobin::Sequence::new("GATCCTAGACTAGATCGTA"));
+  /// assert_eq!(minimizers[0], (1, 24));
+  /// assert_eq!(minimizers.last(), Some(&(14, 53)));
   /// ```
-  pub fn reverse_complement(&self) -> Self {
-    let mut result: Vec<Base> = Vec::new();
-    
-    for i in (0..self.0.len()).rev() {
-      result.push(self.0[i].complement());
+  pub fn minimizers(&self, k: usize, w: usize) -> Vec<(usize, u64)> {
+    if w == 0 {
+      return Vec::new();
+    }
+
+    let canonical: Vec<u64> = self.kmers(k)
+      .map(|kmer| std::cmp::min(kmer, kmer_reverse_complement(kmer, k)))
+      .collect();
+
+    let mut result: Vec<(usize, u64)> = Vec::new();
+
+    for start in 0..canonical.len() {
+      if start + w > canonical.len() {
+        break;
+      }
+
+      let (offset, &value) = canonical[start..start + w].iter().enumerate()
+        .min_by_key(|&(_, &value)| mix_hash(value))
+        .unwrap();
+
+      let selection = (start + offset, value);
+
+      if result.last() != Some(&selection) {
+        result.push(selection);
+      }
+    }
+
+    return result;
+  }
+
+  /// Translate the sequence into amino acids using the standard genetic
+  /// code, reading from the given reading frame. Frames `1`/`2`/`3` start
+  /// translation at offset `0`/`1`/`2`; frames `-1`/`-2`/`-3` apply the same
+  /// offsets to `reverse_complement()` instead. Trailing bases that don't
+  /// fill a whole codon are dropped. `Err(InvalidFrame)` for any other frame
+  ///
+  /// # Examples
+  /// ```
+  /// let a_sequence = hemoglobin::Sequence::new("ATGAAATAG");
+  ///
+  /// assert_eq!(
+  ///   a_sequence.translate(1),
+  ///   Ok(vec![hemoglobin::AminoAcid::Met, hemoglobin::AminoAcid::Lys, hemoglobin::AminoAcid::Stop])
+  /// );
+  /// assert_eq!(a_sequence.translate(0), Err(hemoglobin::InvalidFrame(0)));
+  /// ```
+  pub fn translate(&self, frame: i8) -> Result<Vec<AminoAcid>, InvalidFrame> {
+    let offset = match frame {
+      1 | -1 => 0,
+      2 | -2 => 1,
+      3 | -3 => 2,
+      _ => return Err(InvalidFrame(frame))
+    };
+
+    let reversed;
+    let source = if frame < 0 {
+      reversed = self.reverse_complement();
+      &reversed
+    } else {
+      self
+    };
+
+    let mut result = Vec::new();
+    let mut i = offset;
+
+    while i + 3 <= source.len() {
+      let b0 = source.get(i).unwrap() as usize;
+      let b1 = source.get(i + 1).unwrap() as usize;
+      let b2 = source.get(i + 2).unwrap() as usize;
+
+      result.push(CODON_TABLE[(b0 << 4) | (b1 << 2) | b2]);
+
+      i += 3;
+    }
+
+    return Ok(result);
+  }
+
+  /// Translate all six reading frames (`1`, `2`, `3`, `-1`, `-2`, `-3`, in
+  /// that order)
+  ///
+  /// # Examples
+  /// ```
+  /// let a_sequence = hemoglobin::Sequence::new("ATGAAATAG");
+  /// let frames = a_sequence.six_frame_translation();
+  ///
+  /// assert_eq!(frames[0], a_sequence.translate(1).unwrap());
+  /// assert_eq!(frames[5], a_sequence.translate(-3).unwrap());
+  /// ```
+  pub fn six_frame_translation(&self) -> [Vec<AminoAcid>; 6] {
+    [
+      self.translate(1).unwrap(), self.translate(2).unwrap(), self.translate(3).unwrap(),
+      self.translate(-1).unwrap(), self.translate(-2).unwrap(), self.translate(-3).unwrap()
+    ]
+  }
+}
+
+/// Take the reverse complement of a `k`-mer packed MSB-first the way
+/// `Sequence::kmers` yields them
+fn kmer_reverse_complement(kmer: u64, k: usize) -> u64 {
+  let mask = if k >= 32 { u64::MAX } else { (1 << (2*k)) - 1 };
+  let complemented = kmer ^ mask;
+
+  let mut result: u64 = 0;
+
+  for i in 0..k {
+    result = (result << 2) | ((complemented >> (2*i)) & 0b11);
+  }
+
+  return result;
+}
+
+/// Cheap invertible 64-bit multiply-xor mix (the splitmix64 finalizer),
+/// used to pick minimizers without biasing toward runs of the same base
+fn mix_hash(mut x: u64) -> u64 {
+  x ^= x >> 30;
+  x = x.wrapping_mul(0xbf58476d1ce4e5b9);
+  x ^= x >> 27;
+  x = x.wrapping_mul(0x94d049bb133111eb);
+  x ^= x >> 31;
+
+  return x;
+}
+
+/// Iterator over the k-mers of a `Sequence`, produced by `Sequence::kmers`
+pub struct Kmers<'a> {
+  sequence: &'a Sequence,
+  k: usize,
+  i: usize,
+  register: u64,
+  mask: u64
+}
+
+impl<'a> Iterator for Kmers<'a> {
+  type Item = u64;
+
+  fn next(&mut self) -> Option<u64> {
+    if self.k == 0 || self.k > 32 || self.i + self.k > self.sequence.len() {
+      return None;
+    }
+
+    if self.i == 0 {
+      for j in 0..self.k {
+        self.register = (self.register << 2) | (self.sequence.get(j).unwrap() as u64);
+      }
+    } else {
+      let next_base = self.sequence.get(self.i + self.k - 1).unwrap() as u64;
+      self.register = ((self.register << 2) | next_base) & self.mask;
     }
-    
-    return Sequence(result);
+
+    self.i += 1;
+
+    return Some(self.register);
   }
 }
 
@@ -227,33 +832,108 @@ impl Sequence {
 /// ```
 pub fn find_kmers(sequence: &Sequence, kmer: &Sequence, circular: bool) -> Vec<u64> {
   let mut result: Vec<u64> = Vec::new();
-  
+
   let end = match circular {
-    false => sequence.0.len() - kmer.0.len() + 1,
-    true => sequence.0.len()
+    false => sequence.len() - kmer.len() + 1,
+    true => sequence.len()
   };
-  
+
   for i in 0..end {
     let mut matches = true;
-    
-    for j in 0..kmer.0.len() {
-      if sequence.0[(i + j) % sequence.0.len()] != kmer.0[j] {
+
+    for j in 0..kmer.len() {
+      if sequence.get((i + j) % sequence.len()) != kmer.get(j) {
         matches = false;
       }
     }
-    
+
     if matches {
       result.push(i as u64);
     }
   }
-  
+
+  return result;
+}
+
+/// Pack an entire sequence into the right-aligned register convention used
+/// by `Sequence::kmers`. Limited to 32 bases, same as `subsequence_as_u64`
+fn encode_all(sequence: &Sequence<Dna>) -> Option<u64> {
+  if sequence.len() > 32 {
+    return None;
+  }
+
+  let mut register: u64 = 0;
+
+  for i in 0..sequence.len() {
+    register = (register << 2) | (sequence.get(i).unwrap() as u64);
+  }
+
+  return Some(register);
+}
+
+/// Find all appearances of a kmer in a sequence on either strand, checking
+/// both the kmer's forward encoding and its reverse complement against a
+/// rolling window register so each position is a single integer comparison
+/// rather than a base-by-base scan. Hits are annotated with the strand they
+/// matched on: `'+'` for the given orientation, `'-'` for its reverse
+/// complement. Must specify if the sequence is from a linear or circular
+/// strand. Limited to kmers of at most 32 bases
+///
+/// # Examples
+/// ```
+/// let a_sequence = hemoglobin::Sequence::new("TACGATCTAGTCTAGGATC");
+/// let a_kmer = hemoglobin::Sequence::new("GAT");
+///
+/// let matches = hemoglobin::find_kmers_both_strands(&a_sequence, &a_kmer, false);
+///
+/// assert_eq!(matches, vec![(3, '+'), (4, '-'), (15, '+'), (16, '-')]);
+/// ```
+pub fn find_kmers_both_strands(
+  sequence: &Sequence<Dna>, kmer: &Sequence<Dna>, circular: bool
+) -> Vec<(u64, char)> {
+  let mut result: Vec<(u64, char)> = Vec::new();
+
+  let k = kmer.len();
+
+  let forward = match encode_all(kmer) {
+    Some(value) => value,
+    None => return result
+  };
+  let reverse = kmer_reverse_complement(forward, k);
+  let mask = if k >= 32 { u64::MAX } else { (1 << (2*k)) - 1 };
+
+  let end = match circular {
+    false => sequence.len() - k + 1,
+    true => sequence.len()
+  };
+
+  let mut register: u64 = 0;
+
+  for i in 0..end {
+    if i == 0 {
+      for j in 0..k {
+        register = (register << 2) | (sequence.get((i + j) % sequence.len()).unwrap() as u64);
+      }
+    } else {
+      let next_base = sequence.get((i + k - 1) % sequence.len()).unwrap() as u64;
+      register = ((register << 2) | next_base) & mask;
+    }
+
+    if register == forward {
+      result.push((i as u64, '+'));
+    }
+    if register == reverse {
+      result.push((i as u64, '-'));
+    }
+  }
+
   return result;
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
-  
+
   #[test]
   fn base_complements() {
     assert_eq!(Base::A.complement(), Base::T);
@@ -261,7 +941,7 @@ mod tests {
     assert_eq!(Base::C.complement(), Base::G);
     assert_eq!(Base::G.complement(), Base::C);
   }
-  
+
   #[test]
   fn base_from_u64() {
     assert_eq!(Base::from_u64(0), Some(Base::A));
@@ -270,14 +950,70 @@ mod tests {
     assert_eq!(Base::from_u64(3), Some(Base::T));
     assert_eq!(Base::from_u64(4), None);
   }
-  
+
+  #[test]
+  fn sequence_get() {
+    let a_sequence = Sequence::new("ATCG");
+
+    assert_eq!(a_sequence.get(0), Some(Base::A));
+    assert_eq!(a_sequence.get(3), Some(Base::G));
+    assert_eq!(a_sequence.get(4), None);
+  }
+
+  #[test]
+  fn sequence_try_new() {
+    assert_eq!(Sequence::<Dna>::try_new("ATCG"), Ok(Sequence::new("ATCG")));
+    assert_eq!(Sequence::<Dna>::try_new("ATNG").unwrap_err(), InvalidCharacter('N'));
+  }
+
+  #[test]
+  fn sequence_complement() {
+    let a_sequence = Sequence::new("TACG");
+
+    assert_eq!(a_sequence.complement(), Sequence::new("ATGC"));
+  }
+
+  #[test]
+  fn iupac_sequence_round_trip() {
+    let a_sequence = Sequence::<Iupac>::try_new("ACGTN-RYSWKMBDHV").unwrap();
+
+    assert_eq!(a_sequence.to_string(), "ACGTN-RYSWKMBDHV");
+    assert_eq!(Sequence::<Iupac>::try_new("ACGZ").unwrap_err(), InvalidCharacter('Z'));
+  }
+
+  #[test]
+  fn iupac_complement() {
+    let a_sequence = Sequence::<Iupac>::try_new("ACGTN-RYSWKMBDHV").unwrap();
+
+    assert_eq!(a_sequence.complement().to_string(), "TGCAN-YRSWMKVHDB");
+  }
+
+  #[test]
+  fn iupac_reverse_complement() {
+    // 17 symbols, one more than a 4-bit codec's 16 symbols/word, so this
+    // exercises the partial trailing word across a codec other than Dna
+    let a_sequence = Sequence::<Iupac>::try_new("ACGTNRYSWKMBDHVAC").unwrap();
+
+    assert_eq!(a_sequence.reverse_complement().to_string(), "GTBDHVKMWSRYNACGT");
+  }
+
   #[test]
   fn sequence_reverse_complement() {
     let a_sequence = Sequence::new("TACGATCTAGTCTAGGATC");
-    
+
     assert_eq!(a_sequence.reverse_complement(), Sequence::new("GATCCTAGACTAGATCGTA"));
   }
-  
+
+  #[test]
+  fn sequence_reverse_complement_spans_multiple_words() {
+    let a_sequence = Sequence::new("TACGATCTAGTCTAGGATCTACGATCTAGTCTAGGATCAA");
+
+    assert_eq!(
+      a_sequence.reverse_complement(),
+      Sequence::new("TTGATCCTAGACTAGATCGTAGATCCTAGACTAGATCGTA")
+    );
+  }
+
   #[test]
   fn sequence_from_u64() {
     assert_eq!(Sequence::from_u64(0xBA5E500000000000, 10), Sequence::new("GTGGCCTGCC"));
@@ -285,7 +1021,7 @@ mod tests {
     assert_eq!(Sequence::from_u64(0xBA5E500000000000, 12), Sequence::new("GTGGCCTGCCAA"));
     assert_eq!(Sequence::from_u64(0xBA5E500000000000, 99), Sequence::new("GTGGCCTGCCAAAAAAAAAAAAAAAAAAAAAA"));
   }
-  
+
   #[test]
   fn subsequence_as_u64() {
     let a_sequence = Sequence::new("TACGATCTAGT");
@@ -293,31 +1029,136 @@ mod tests {
     assert_eq!(a_sequence.subsequence_as_u64(4, 0), Some(0));
     assert_eq!(a_sequence.subsequence_as_u64(4, 8), None);
   }
-  
+
   #[test]
   fn count_bases() {
     assert_eq!(Sequence::new("").count_bases(), BaseCount { A: 0, C: 0, G: 0, T: 0 });
     assert_eq!(Sequence::new("TACGATCTAGTCTAGGATC").count_bases(), BaseCount { A: 5, C: 4, G: 4, T: 6 });
   }
-  
+
+  #[test]
+  fn sequence_kmers() {
+    let a_sequence = Sequence::new("ATCGA");
+
+    assert_eq!(a_sequence.kmers(3).collect::<Vec<u64>>(), vec![0xd, 0x36, 0x18]);
+    assert_eq!(a_sequence.kmers(0).collect::<Vec<u64>>(), Vec::<u64>::new());
+    assert_eq!(a_sequence.kmers(6).collect::<Vec<u64>>(), Vec::<u64>::new());
+  }
+
+  #[test]
+  fn sequence_minimizers() {
+    let a_sequence = Sequence::new("TACGATCTAGTCTAGGATC");
+
+    assert_eq!(a_sequence.minimizers(4, 3), vec![
+      (1, 24), (3, 141), (4, 35), (5, 200), (8, 45), (9, 33), (10, 200), (13, 40), (14, 53)
+    ]);
+  }
+
+  #[test]
+  fn sequence_translate() {
+    let a_sequence = Sequence::new("ATGAAATAG");
+
+    assert_eq!(a_sequence.translate(1).unwrap(), vec![AminoAcid::Met, AminoAcid::Lys, AminoAcid::Stop]);
+    // Frame 2 drops the leading A and the trailing AG, leaving two codons
+    assert_eq!(a_sequence.translate(2).unwrap(), vec![AminoAcid::Stop, AminoAcid::Asn]);
+  }
+
+  #[test]
+  fn sequence_translate_negative_frame() {
+    let a_sequence = Sequence::new("ATGAAATAG");
+
+    assert_eq!(a_sequence.translate(-1), a_sequence.reverse_complement().translate(1));
+  }
+
+  #[test]
+  fn sequence_translate_invalid_frame() {
+    let a_sequence = Sequence::new("ATGAAATAG");
+
+    assert_eq!(a_sequence.translate(0), Err(InvalidFrame(0)));
+    assert_eq!(a_sequence.translate(4), Err(InvalidFrame(4)));
+    // i8::MIN has no positive representation, so it must be rejected
+    // without ever negating the frame
+    assert_eq!(a_sequence.translate(i8::MIN), Err(InvalidFrame(i8::MIN)));
+  }
+
+  #[test]
+  fn sequence_six_frame_translation() {
+    let a_sequence = Sequence::new("ATGAAATAG");
+    let frames = a_sequence.six_frame_translation();
+
+    assert_eq!(frames.len(), 6);
+    assert_eq!(frames[0], a_sequence.translate(1).unwrap());
+    assert_eq!(frames[4], a_sequence.translate(-2).unwrap());
+  }
+
   #[test]
   fn find_kmers_linear() {
     let a_sequence = Sequence::new("TACGATCTAGTCTAGGATC");
     let a_kmer = Sequence::new("TCTA");
-    
+
     let matches = find_kmers(&a_sequence, &a_kmer, false);
-    
+
     assert_eq!(matches, vec![5, 10]);
   }
-  
+
   #[test]
   fn find_kmers_circular() {
     let a_sequence = Sequence::new("TACGATCTAGTCTAGGATC");
     let a_kmer = Sequence::new("TCTA");
-    
+
     let matches = find_kmers(&a_sequence, &a_kmer, true);
-    
+
     assert_eq!(matches, vec![5, 10, 17]);
   }
-}
 
+  #[test]
+  fn find_kmers_both_strands_linear() {
+    let a_sequence = Sequence::new("TACGATCTAGTCTAGGATC");
+
+    assert_eq!(
+      find_kmers_both_strands(&a_sequence, &Sequence::new("GAT"), false),
+      vec![(3, '+'), (4, '-'), (15, '+'), (16, '-')]
+    );
+    // A kmer with no hits at all on the given sequence
+    assert_eq!(find_kmers_both_strands(&a_sequence, &Sequence::new("GGG"), false), Vec::new());
+  }
+
+  #[test]
+  fn find_kmers_both_strands_circular() {
+    let a_sequence = Sequence::new("TACGATCTAGTCTAGGATC");
+
+    assert_eq!(
+      find_kmers_both_strands(&a_sequence, &Sequence::new("GAT"), true),
+      vec![(3, '+'), (4, '-'), (15, '+'), (16, '-')]
+    );
+  }
+
+  #[test]
+  fn from_reader_plain_text() {
+    let contents = std::io::Cursor::new(b"ATCG".to_vec());
+
+    assert_eq!(Sequence::from_reader(contents).unwrap(), Sequence::new("ATCG"));
+  }
+
+  #[test]
+  fn from_reader_short_plain_text() {
+    // Shorter than the gzip magic-byte header, so the header-peek loop must
+    // stop at EOF instead of looping forever
+    let contents = std::io::Cursor::new(b"A".to_vec());
+
+    assert_eq!(Sequence::from_reader(contents).unwrap(), Sequence::new("A"));
+  }
+
+  #[test]
+  fn from_reader_gzip() {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(b"ATCG").unwrap();
+    let compressed = encoder.finish().unwrap();
+
+    assert_eq!(Sequence::from_reader(std::io::Cursor::new(compressed)).unwrap(), Sequence::new("ATCG"));
+  }
+}